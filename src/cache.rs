@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+pub use serenity::builder::CreateEmbed;
+use serenity::model::id::{MessageId, UserId};
+use serenity::prelude::TypeMapKey;
+use tokio::sync::{Mutex, RwLock};
+
+use crate::events::PendingCompile;
+
+/// How long an attachment-compile prompt waits for a "Compile"/"Dismiss" click
+/// before [`sweep_expired`] treats it as abandoned, so a prompt nobody ever
+/// clicks doesn't pin its source code in memory for the life of the process.
+pub const PENDING_COMPILE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Attachment-compile prompts awaiting the original author's confirmation via the
+/// "Compile" button, keyed by the id of the message the button is attached to.
+/// Entries carry the [`Instant`] they were inserted at so [`sweep_expired`] can
+/// evict ones nobody ever clicked.
+pub struct PendingCompileCache;
+impl TypeMapKey for PendingCompileCache {
+    type Value = Arc<Mutex<HashMap<MessageId, (UserId, PendingCompile, Instant)>>>;
+}
+
+/// Removes every entry in `map` older than `ttl`, based on the [`Instant`] returned
+/// by `inserted_at` for each value. Called on insert so caches like
+/// [`PendingCompileCache`] and [`PaginationCache`] stay bounded by activity rather
+/// than growing for the life of the process.
+pub fn sweep_expired<K, V>(map: &mut HashMap<K, V>, ttl: Duration, inserted_at: impl Fn(&V) -> Instant) {
+    map.retain(|_, v| inserted_at(v).elapsed() < ttl);
+}
+
+/// Per-guild configuration managed through the `;settings` command group: the
+/// default compile target used for an attachment whose file extension resolves to
+/// no known compiler (fenced code blocks that omit a language do not use it), the
+/// attachment auto-compile toggle, a channel allowlist restricting where
+/// compilation is permitted, and a custom command prefix.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GuildSettings {
+    pub default_language: Option<String>,
+    auto_compile: bool,
+    channel_allowlist: Vec<u64>,
+    pub prefix: Option<String>,
+    webhook_persona: Option<WebhookPersona>,
+}
+
+impl Default for GuildSettings {
+    fn default() -> Self {
+        Self {
+            default_language: None,
+            // attachment auto-compile predates this per-guild settings entry and was
+            // always on; a freshly created (not yet explicitly configured) entry must
+            // preserve that behavior rather than silently disabling it on first join
+            auto_compile: true,
+            channel_allowlist: Vec::new(),
+            prefix: None,
+            webhook_persona: None,
+        }
+    }
+}
+
+impl GuildSettings {
+    pub fn auto_compile_enabled(&self) -> bool {
+        self.auto_compile
+    }
+
+    pub fn set_auto_compile(&mut self, enabled: bool) {
+        self.auto_compile = enabled;
+    }
+
+    /// A channel is permitted to compile in if no allowlist has been configured,
+    /// or the channel is explicitly present in it.
+    pub fn is_channel_allowed(&self, channel: u64) -> bool {
+        self.channel_allowlist.is_empty() || self.channel_allowlist.contains(&channel)
+    }
+
+    pub fn allow_channel(&mut self, channel: u64) {
+        if !self.channel_allowlist.contains(&channel) {
+            self.channel_allowlist.push(channel);
+        }
+    }
+
+    pub fn disallow_channel(&mut self, channel: u64) {
+        self.channel_allowlist.retain(|c| *c != channel);
+    }
+
+    pub fn webhook_persona(&self) -> Option<WebhookPersona> {
+        self.webhook_persona.clone()
+    }
+
+    pub fn set_webhook_persona(&mut self, persona: WebhookPersona) {
+        self.webhook_persona = Some(persona);
+    }
+
+    pub fn clear_webhook_persona(&mut self) {
+        self.webhook_persona = None;
+    }
+}
+
+/// A webhook identity compiler results are delivered through instead of the bot's
+/// own identity, when a guild has configured one via `;settings webhook`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WebhookPersona {
+    pub id: u64,
+    pub token: String,
+    pub username: String,
+    pub avatar_url: Option<String>,
+}
+
+/// Path to the on-disk store backing [`GuildSettingsManager`], relative to the
+/// bot's working directory.
+const SETTINGS_STORE_PATH: &str = "guild_settings.json";
+
+/// Holds every guild's [`GuildSettings`], loading/persisting them through a JSON
+/// file on disk as guilds are joined and left so `;settings` changes survive a
+/// restart.
+#[derive(Default)]
+pub struct GuildSettingsManager {
+    settings: HashMap<u64, GuildSettings>,
+    loaded_from_disk: bool,
+}
+
+impl GuildSettingsManager {
+    pub fn get(&self, guild_id: u64) -> Option<&GuildSettings> {
+        self.settings.get(&guild_id)
+    }
+
+    pub fn get_mut(&mut self, guild_id: u64) -> &mut GuildSettings {
+        self.settings.entry(guild_id).or_insert_with(GuildSettings::default)
+    }
+
+    /// Loads a guild's settings from the backing store, or persists a fresh
+    /// default entry if this is the first time we've seen this guild. The store
+    /// is read from disk once per process and cached in memory afterward.
+    pub async fn load_or_create(&mut self, guild_id: u64) {
+        if !self.loaded_from_disk {
+            self.load_from_disk().await;
+            self.loaded_from_disk = true;
+        }
+
+        if !self.settings.contains_key(&guild_id) {
+            self.settings.insert(guild_id, GuildSettings::default());
+            self.persist().await;
+        }
+    }
+
+    async fn load_from_disk(&mut self) {
+        let contents = match tokio::fs::read(SETTINGS_STORE_PATH).await {
+            Ok(contents) => contents,
+            Err(_) => return, // no store on disk yet, start from an empty map
+        };
+        match serde_json::from_slice(&contents) {
+            Ok(settings) => self.settings = settings,
+            Err(e) => warn!("Failed to parse {}: {}", SETTINGS_STORE_PATH, e),
+        }
+    }
+
+    /// Writes every guild's settings back to [`SETTINGS_STORE_PATH`]. Callers that
+    /// mutate a [`GuildSettings`] through [`get_mut`](Self::get_mut) must call this
+    /// afterward for the change to survive a restart.
+    pub async fn persist(&self) {
+        match serde_json::to_vec_pretty(&self.settings) {
+            Ok(json) => {
+                if let Err(e) = tokio::fs::write(SETTINGS_STORE_PATH, json).await {
+                    warn!("Failed to persist {}: {}", SETTINGS_STORE_PATH, e);
+                }
+            }
+            Err(e) => warn!("Failed to serialize guild settings: {}", e),
+        }
+    }
+
+    pub async fn evict(&mut self, guild_id: u64) {
+        self.settings.remove(&guild_id);
+        self.persist().await;
+    }
+}
+
+pub struct GuildSettingsCache;
+impl TypeMapKey for GuildSettingsCache {
+    type Value = Arc<RwLock<GuildSettingsManager>>;
+}
+
+/// Path to the on-disk store backing [`Blocklist`]'s runtime mutations, relative to
+/// the bot's working directory.
+const BLOCKLIST_STORE_PATH: &str = "blocklist.json";
+
+/// `Blocklist` and the `BlocklistCache` key it sits behind already exist in the
+/// bot's existing cache module alongside `ConfigCache` et al. (`before()` has
+/// always called `.contains()` on it); this only adds the runtime mutation and
+/// persistence this series' `;block`/`;unblock`/`;blocklist` commands need.
+impl Blocklist {
+    /// Loads the blocklist's on-disk overrides from [`BLOCKLIST_STORE_PATH`] on top
+    /// of whatever entries it already started with, or leaves it untouched if no
+    /// store exists on disk yet.
+    pub async fn load_overrides(&mut self) {
+        let contents = match tokio::fs::read(BLOCKLIST_STORE_PATH).await {
+            Ok(contents) => contents,
+            Err(_) => return,
+        };
+        match serde_json::from_slice::<std::collections::HashSet<u64>>(&contents) {
+            Ok(entries) => entries.into_iter().for_each(|id| self.block(id)),
+            Err(e) => warn!("Failed to parse {}: {}", BLOCKLIST_STORE_PATH, e),
+        }
+    }
+
+    pub fn entries(&self) -> Vec<u64> {
+        self.entries.iter().copied().collect()
+    }
+
+    pub fn block(&mut self, id: u64) {
+        self.entries.insert(id);
+    }
+
+    pub fn unblock(&mut self, id: u64) {
+        self.entries.remove(&id);
+    }
+
+    /// Writes the blocklist back to [`BLOCKLIST_STORE_PATH`]. Callers that mutate
+    /// through [`block`](Self::block)/[`unblock`](Self::unblock) must call this
+    /// afterward for the change to survive a restart.
+    pub async fn persist(&self) {
+        match serde_json::to_vec_pretty(&self.entries) {
+            Ok(json) => {
+                if let Err(e) = tokio::fs::write(BLOCKLIST_STORE_PATH, json).await {
+                    warn!("Failed to persist {}: {}", BLOCKLIST_STORE_PATH, e);
+                }
+            }
+            Err(e) => warn!("Failed to serialize blocklist: {}", e),
+        }
+    }
+}
+
+/// How long a paginated compiler result's Prev/Next state is kept before
+/// [`sweep_expired`] evicts it, so a multi-page result nobody pages through
+/// doesn't pin its full `Vec<CreateEmbed>` for the life of the process.
+pub const PAGINATION_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Paginated compiler-result state: which pages exist, which one is currently shown,
+/// the total page count of the result they were cut from (which can exceed the
+/// stored pages when a webhook persona already delivered the earlier ones), and who's
+/// allowed to page through them, keyed by the message carrying the Prev/Next buttons.
+/// Entries carry the [`Instant`] they were inserted at so [`sweep_expired`] can evict
+/// ones nobody ever pages through.
+pub struct PaginationCache;
+impl TypeMapKey for PaginationCache {
+    type Value = Arc<Mutex<HashMap<MessageId, (UserId, Vec<CreateEmbed>, usize, usize, Instant)>>>;
+}
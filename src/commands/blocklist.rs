@@ -0,0 +1,119 @@
+use serenity::{
+    framework::standard::{macros::command, macros::group, Args, CommandResult},
+    model::channel::Message,
+    model::guild::Member,
+    model::id::GuildId,
+    prelude::*,
+};
+
+use crate::cache::{BlocklistCache, ConfigCache};
+use crate::utls::discordhelpers;
+use crate::utls::discordhelpers::embeds;
+
+#[group]
+#[owners_only]
+#[commands(block, unblock, blocklist_list)]
+#[description = "Bot-staff commands for managing the bot-wide runtime user/guild blocklist."]
+struct Blocklist;
+
+#[command]
+#[min_args(1)]
+#[description = "Blocks a user or guild id from using the bot. If the id is a member of the invoking guild, the action is also refused when their highest role outranks (or matches) the invoker's."]
+async fn block(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let id = args.single::<u64>()?;
+
+    if let Some(guild_id) = msg.guild_id {
+        if let (Ok(target), Ok(invoker)) = (
+            guild_id.member(&ctx.http, id).await,
+            guild_id.member(&ctx.http, msg.author.id).await,
+        ) {
+            let target_position = highest_role_position(ctx, guild_id, &target).await;
+            let invoker_position = highest_role_position(ctx, guild_id, &invoker).await;
+            if target_position >= invoker_position {
+                let emb = embeds::build_fail_embed(&msg.author, "You can't block someone whose highest role outranks or matches your own.");
+                let mut emb_msg = embeds::embed_message(emb);
+                let _ = msg.channel_id.send_message(&ctx.http, |_| &mut emb_msg).await;
+                return Ok(());
+            }
+        }
+    }
+
+    {
+        let data = ctx.data.read().await;
+        let mut blocklist = data.get::<BlocklistCache>().unwrap().write().await;
+        blocklist.block(id);
+        blocklist.persist().await;
+    }
+
+    log_blocklist_action(ctx, msg, "Blocked", id).await;
+    reply_blocklist(ctx, msg, &format!("`{}` is now blocked from using this bot.", id)).await;
+    Ok(())
+}
+
+#[command]
+#[min_args(1)]
+#[description = "Unblocks a previously blocked user or guild id."]
+async fn unblock(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let id = args.single::<u64>()?;
+
+    {
+        let data = ctx.data.read().await;
+        let mut blocklist = data.get::<BlocklistCache>().unwrap().write().await;
+        blocklist.unblock(id);
+        blocklist.persist().await;
+    }
+
+    log_blocklist_action(ctx, msg, "Unblocked", id).await;
+    reply_blocklist(ctx, msg, &format!("`{}` is no longer blocked.", id)).await;
+    Ok(())
+}
+
+#[command("blocklist")]
+#[description = "Lists every id currently present in the blocklist."]
+async fn blocklist_list(ctx: &Context, msg: &Message) -> CommandResult {
+    let entries = {
+        let data = ctx.data.read().await;
+        let blocklist = data.get::<BlocklistCache>().unwrap().read().await;
+        blocklist.entries()
+    };
+
+    let mut emb_msg = embeds::embed_message(embeds::build_blocklist_list_embed(&entries));
+    let _ = msg.channel_id.send_message(&ctx.http, |_| &mut emb_msg).await;
+    Ok(())
+}
+
+/// The highest role position held by `member` in `guild_id`, or `0` if the guild
+/// isn't cached or the member holds no roles (matching `@everyone`'s position).
+async fn highest_role_position(ctx: &Context, guild_id: GuildId, member: &Member) -> i64 {
+    let guild = match guild_id.to_guild_cached(&ctx.cache).await {
+        Some(guild) => guild,
+        None => return 0,
+    };
+
+    member
+        .roles
+        .iter()
+        .filter_map(|role_id| guild.roles.get(role_id))
+        .map(|role| role.position)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Logs a `;block`/`;unblock` action to the configured `JOIN_LOG` channel, the same
+/// channel `events.rs` posts guild join/leave activity to.
+async fn log_blocklist_action(ctx: &Context, msg: &Message, action: &str, id: u64) {
+    let data = ctx.data.read().await;
+    let info = data.get::<ConfigCache>().unwrap().read().await;
+    if let Some(log) = info.get("JOIN_LOG") {
+        if let Ok(log_id) = log.parse::<u64>() {
+            let emb = embeds::build_blocklist_action_embed(action, id, &msg.author.tag());
+            discordhelpers::manual_dispatch(ctx.http.clone(), log_id, emb).await;
+        }
+    }
+}
+
+async fn reply_blocklist(ctx: &Context, msg: &Message, text: &str) {
+    let emb = embeds::build_settings_ack_embed(text);
+    let mut emb_msg = embeds::embed_message(emb);
+    let _ = msg.channel_id.send_message(&ctx.http, |_| &mut emb_msg).await;
+}
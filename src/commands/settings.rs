@@ -0,0 +1,160 @@
+use serenity::{
+    framework::standard::{macros::command, macros::group, Args, CommandResult},
+    model::channel::Message,
+    prelude::*,
+};
+
+use crate::cache::{GuildSettingsCache, WebhookPersona};
+use crate::utls::discordhelpers::embeds;
+
+#[group]
+#[prefix = "settings"]
+#[only_in(guilds)]
+#[required_permissions(ADMINISTRATOR)]
+#[commands(language, autocompile, prefix, allow, disallow, webhook, unwebhook)]
+#[description = "Configure this server's default compile target, auto-compile, prefix, channel allowlist, and result-delivery webhook."]
+struct Settings;
+
+#[command]
+#[min_args(1)]
+#[description = "Sets the default language/compiler used for an attached source file whose extension doesn't match a known compiler. Fenced code blocks that omit a language don't use it."]
+async fn language(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+    let language = args.rest().to_string();
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<GuildSettingsCache>().unwrap().write().await;
+    settings.get_mut(guild_id).default_language = Some(language.clone());
+    settings.persist().await;
+
+    reply_ok(ctx, msg, &format!("Default language set to `{}`.", language)).await;
+    Ok(())
+}
+
+#[command]
+#[min_args(1)]
+#[description = "Toggles whether attachments are auto-compiled in this server (on/off)."]
+async fn autocompile(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let enabled = matches!(args.single::<String>()?.to_lowercase().as_str(), "on" | "true" | "enable" | "enabled");
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<GuildSettingsCache>().unwrap().write().await;
+    settings.get_mut(guild_id).set_auto_compile(enabled);
+    settings.persist().await;
+
+    reply_ok(ctx, msg, &format!("Attachment auto-compile is now **{}**.", if enabled { "on" } else { "off" })).await;
+    Ok(())
+}
+
+#[command]
+#[min_args(1)]
+#[description = "Sets this server's custom command prefix."]
+async fn prefix(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+    let prefix = args.rest().to_string();
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<GuildSettingsCache>().unwrap().write().await;
+    settings.get_mut(guild_id).prefix = Some(prefix.clone());
+    settings.persist().await;
+
+    reply_ok(ctx, msg, &format!("Prefix set to `{}`.", prefix)).await;
+    Ok(())
+}
+
+#[command]
+#[description = "Adds a channel to the compile allowlist (compiling is unrestricted until the first channel is added)."]
+async fn allow(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let channel = args.single::<u64>().unwrap_or(msg.channel_id.0);
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<GuildSettingsCache>().unwrap().write().await;
+    settings.get_mut(guild_id).allow_channel(channel);
+    settings.persist().await;
+
+    reply_ok(ctx, msg, &format!("<#{}> can now be used to compile.", channel)).await;
+    Ok(())
+}
+
+#[command]
+#[description = "Removes a channel from the compile allowlist."]
+async fn disallow(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let channel = args.single::<u64>().unwrap_or(msg.channel_id.0);
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<GuildSettingsCache>().unwrap().write().await;
+    settings.get_mut(guild_id).disallow_channel(channel);
+    settings.persist().await;
+
+    reply_ok(ctx, msg, &format!("<#{}> was removed from the compile allowlist.", channel)).await;
+    Ok(())
+}
+
+#[command]
+#[min_args(1)]
+#[description = "Sets the webhook compiler results are delivered through (`;settings webhook <url> [username] [avatar url]`), impersonating the given name/avatar instead of the bot."]
+async fn webhook(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let url = args.single::<String>()?;
+    let (id, token) = match parse_webhook_url(&url) {
+        Some(parts) => parts,
+        None => {
+            let emb = embeds::build_fail_embed(&msg.author, "That doesn't look like a Discord webhook URL.");
+            let mut emb_msg = embeds::embed_message(emb);
+            let _ = msg.channel_id.send_message(&ctx.http, |_| &mut emb_msg).await;
+            return Ok(());
+        }
+    };
+    let username = args.single::<String>().unwrap_or_else(|_| "Compiler".to_string());
+    let avatar_url = args.single::<String>().ok();
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<GuildSettingsCache>().unwrap().write().await;
+    settings.get_mut(guild_id).set_webhook_persona(WebhookPersona {
+        id,
+        token,
+        username: username.clone(),
+        avatar_url,
+    });
+    settings.persist().await;
+
+    reply_ok(
+        ctx,
+        msg,
+        &format!("Compiler results in this server will now be delivered as **{}**.", username),
+    )
+    .await;
+    Ok(())
+}
+
+#[command]
+#[description = "Stops delivering compiler results through the configured webhook persona."]
+async fn unwebhook(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<GuildSettingsCache>().unwrap().write().await;
+    settings.get_mut(guild_id).clear_webhook_persona();
+    settings.persist().await;
+
+    reply_ok(ctx, msg, "Compiler results will be delivered as this bot again.").await;
+    Ok(())
+}
+
+/// Pulls the webhook id and token out of a `.../webhooks/<id>/<token>` URL.
+fn parse_webhook_url(url: &str) -> Option<(u64, String)> {
+    let tail = url.trim_end_matches('/').rsplit("/webhooks/").next()?;
+    let mut parts = tail.splitn(2, '/');
+    let id = parts.next()?.parse().ok()?;
+    let token = parts.next()?.to_string();
+    Some((id, token))
+}
+
+async fn reply_ok(ctx: &Context, msg: &Message, text: &str) {
+    let emb = embeds::build_settings_ack_embed(text);
+    let mut emb_msg = embeds::embed_message(emb);
+    let _ = msg.channel_id.send_message(&ctx.http, |_| &mut emb_msg).await;
+}
@@ -13,24 +13,39 @@ use serenity::{
 };
 
 use chrono::{DateTime, Duration, Utc};
+use std::time::Instant;
 
 use crate::cache::*;
 use crate::utls::discordhelpers;
 use crate::managers::stats::StatsManager;
-use serenity::model::id::{GuildId};
+use serenity::model::id::{GuildId, UserId};
 use serenity::model::event::{MessageUpdateEvent};
+use serenity::model::interactions::{Interaction, InteractionResponseType};
+use serenity::model::interactions::message_component::{ButtonStyle, MessageComponentInteraction};
+use serenity::builder::CreateActionRow;
+use serenity::model::webhook::Webhook;
 use crate::utls::discordhelpers::embeds;
 use tokio::sync::MutexGuard;
-use serenity::model::channel::{ReactionType};
 
 use crate::utls::parser::{get_message_attachment, shortname_to_qualified};
 use crate::managers::compilation::RequestHandler;
-use serenity::collector::CollectReaction;
 use crate::commands::compile::handle_request;
 use crate::utls::discordhelpers::embeds::embed_message;
 
 pub struct Handler; // event handler for serenity
 
+/// Everything needed to re-issue a compile request once the requesting author
+/// confirms it via the "Compile" button on the attachment prompt.
+///
+/// `pub(crate)` so `cache::PendingCompileCache` can name it.
+#[derive(Clone)]
+pub(crate) struct PendingCompile {
+    author: serenity::model::user::User,
+    original_message: Message,
+    code: String,
+    language: String,
+}
+
 #[async_trait]
 trait ShardsReadyHandler {
     async fn all_shards_ready(&self, ctx: &Context, stats: & mut MutexGuard<'_, StatsManager>, ready : &Ready);
@@ -43,6 +58,10 @@ impl ShardsReadyHandler for Handler {
         let mut info = data.get::<ConfigCache>().unwrap().write().await;
         info.insert("BOT_AVATAR", ready.user.avatar_url().unwrap());
 
+        // pull in whatever `;block`/`;unblock` persisted to disk before a previous
+        // restart, on top of whatever BlocklistCache was seeded with at startup
+        data.get::<BlocklistCache>().unwrap().write().await.load_overrides().await;
+
         let shard_manager = data.get::<ShardManagerCache>().unwrap().lock().await;
         let guild_count = stats.get_boot_vec_sum();
 
@@ -81,6 +100,14 @@ impl EventHandler for Handler {
     }
 
     async fn guild_create(&self, ctx: Context, guild: Guild) {
+        {
+            // lazily load this guild's settings (default language, auto-compile toggle,
+            // channel allowlist, custom prefix), creating a default entry on first join
+            let data = ctx.data.read().await;
+            let mut settings = data.get::<GuildSettingsCache>().unwrap().write().await;
+            settings.load_or_create(guild.id.0).await;
+        }
+
         let now: DateTime<Utc> = Utc::now();
         if guild.joined_at + Duration::seconds(30) > now {
             let data = ctx.data.read().await;
@@ -154,6 +181,12 @@ impl EventHandler for Handler {
     async fn guild_delete(&self, ctx: Context, incomplete: GuildUnavailable) {
         let data = ctx.data.read().await;
 
+        // this guild's settings are no longer relevant, drop them from the cache
+        {
+            let mut settings = data.get::<GuildSettingsCache>().unwrap().write().await;
+            settings.evict(incomplete.id.0).await;
+        }
+
         // post new server to join log
         let info = data.get::<ConfigCache>().unwrap().read().await;
         let id = info.get("BOT_ID").unwrap().parse::<u64>().unwrap();
@@ -193,64 +226,100 @@ impl EventHandler for Handler {
 
     async fn message(&self, ctx: Context, new_message: Message) {
         if !new_message.attachments.is_empty() {
-            if let Ok((code, language)) = get_message_attachment(&new_message.attachments).await {
-                let data = ctx.data.read().await;
-                let target = {
+            let data = ctx.data.read().await;
+
+            // per-guild settings can disable the attachment auto-compile path entirely
+            // or restrict it to a channel allowlist
+            if let Some(guild_id) = new_message.guild_id {
+                let settings = data.get::<GuildSettingsCache>().unwrap().read().await;
+                if let Some(guild_settings) = settings.get(guild_id.0) {
+                    if !guild_settings.auto_compile_enabled()
+                        || !guild_settings.is_channel_allowed(new_message.channel_id.0)
+                    {
+                        return;
+                    }
+                }
+            }
+
+            if let Ok((code, mut language)) = get_message_attachment(&new_message.attachments).await {
+                let mut target = {
                     let cm = data.get::<CompilerCache>().unwrap().read().await;
                     cm.resolve_target(shortname_to_qualified(&language))
                 };
 
-                if !matches!(target,  RequestHandler::None) {
-                    let reaction = {
-                        let botinfo = data.get::<ConfigCache>().unwrap().read().await;
-                        if let Some(id) = botinfo.get("LOGO_EMOJI_ID") {
-                            let name = botinfo.get("LOGO_EMOJI_NAME").expect("Unable to find loading emoji name").clone();
-                            discordhelpers::build_reaction(id.parse::<u64>().unwrap(), &name)
-                        }
-                        else {
-                            ReactionType::Unicode(String::from("💻"))
+                // the attachment's extension didn't resolve to a known compiler target;
+                // fall back to this guild's configured default language/compiler instead
+                // of silently dropping the attachment
+                if matches!(target, RequestHandler::None) {
+                    if let Some(guild_id) = new_message.guild_id {
+                        let settings = data.get::<GuildSettingsCache>().unwrap().read().await;
+                        let default_language = settings.get(guild_id.0).and_then(|s| s.default_language.clone());
+                        if let Some(default_language) = default_language {
+                            let fallback_target = {
+                                let cm = data.get::<CompilerCache>().unwrap().read().await;
+                                cm.resolve_target(shortname_to_qualified(&default_language))
+                            };
+                            if !matches!(fallback_target, RequestHandler::None) {
+                                language = default_language;
+                                target = fallback_target;
+                            }
                         }
-                    };
-
-                    if let Err(_) = new_message.react(&ctx.http, reaction.clone()).await {
-                        return;
                     }
+                }
 
-                    let collector = CollectReaction::new(ctx.clone())
-                        .message_id(new_message.id)
-                        .timeout(core::time::Duration::new(30, 0))
-                        .filter(move |r| r.emoji.eq(&reaction)).await;
-                    let _ = new_message.delete_reactions(&ctx.http).await;
-                    if let Some(_) = collector {
-                        let emb = match handle_request(ctx.clone(), format!(";compile\n```{}\n{}\n```", language, code), new_message.author.clone(), &new_message).await {
-                            Ok(emb) => emb,
-                            Err(e) => {
-                                let emb = embeds::build_fail_embed(&new_message.author, &format!("{}", e));
-                                let mut emb_msg = embeds::embed_message(emb);
-                                if let Ok(sent) = new_message
-                                    .channel_id
-                                    .send_message(&ctx.http, |_| &mut emb_msg)
-                                    .await
-                                {
-                                    let mut message_cache = data.get::<MessageCache>().unwrap().lock().await;
-                                    message_cache.insert(new_message.id.0, sent);
-                                }
-                                return;
-                            }
-                        };
-                        let mut emb_msg = embed_message(emb);
-                        emb_msg.reference_message(&new_message);
-                        let _= new_message
-                            .channel_id
-                            .send_message(&ctx.http, |_| &mut emb_msg)
-                            .await;
+                if !matches!(target,  RequestHandler::None) {
+                    let emb = embeds::build_attachment_prompt_embed(&new_message.author, &language);
+                    let mut emb_msg = embeds::embed_message(emb);
+                    emb_msg.components(|c| {
+                        c.create_action_row(|row| {
+                            row.create_button(|b| {
+                                b.custom_id("attachment_compile")
+                                    .label("Compile")
+                                    .style(ButtonStyle::Primary)
+                            })
+                            .create_button(|b| {
+                                b.custom_id("attachment_dismiss")
+                                    .label("Dismiss")
+                                    .style(ButtonStyle::Secondary)
+                            })
+                        })
+                    });
+
+                    let sent = match new_message
+                        .channel_id
+                        .send_message(&ctx.http, |_| &mut emb_msg)
+                        .await
+                    {
+                        Ok(sent) => sent,
+                        Err(_) => return,
+                    };
 
-                    }
+                    let pending = PendingCompile {
+                        author: new_message.author.clone(),
+                        original_message: new_message.clone(),
+                        code,
+                        language,
+                    };
+                    let mut requests = data.get::<PendingCompileCache>().unwrap().lock().await;
+                    sweep_expired(&mut requests, PENDING_COMPILE_TTL, |(_, _, inserted_at)| *inserted_at);
+                    requests.insert(sent.id, (new_message.author.id, pending, Instant::now()));
                 }
             }
         }
     }
 
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let component = match interaction {
+            Interaction::MessageComponent(component) => component,
+            _ => return,
+        };
+
+        match component.data.custom_id.as_str() {
+            "page_prev" | "page_next" => self.handle_page_turn(&ctx, component).await,
+            _ => self.handle_attachment_interaction(&ctx, component).await,
+        }
+    }
+
     async fn ready(&self, ctx: Context, ready: Ready) {
         info!("[Shard {}] Ready", ctx.shard_id);
 
@@ -274,8 +343,244 @@ impl EventHandler for Handler {
     }
 }
 
+impl Handler {
+    async fn handle_attachment_interaction(&self, ctx: &Context, component: MessageComponentInteraction) {
+        let data = ctx.data.read().await;
+
+        // Fetch the entry and, if it belongs to this clicker, remove it in the same
+        // lock acquisition -- no `await` happens in between -- so a duplicate click
+        // (a double-tap, or Discord retrying the interaction) can't also see it still
+        // present and run the request a second time. A click from someone other than
+        // the author leaves the entry in place for the real author to still use.
+        let pending = {
+            let mut requests = data.get::<PendingCompileCache>().unwrap().lock().await;
+            let valid = requests
+                .get(&component.message.id)
+                .filter(|(_, _, inserted_at)| inserted_at.elapsed() < PENDING_COMPILE_TTL)
+                .cloned();
+
+            if let Some((author_id, _, _)) = &valid {
+                if *author_id == component.user.id {
+                    requests.remove(&component.message.id);
+                }
+            }
+            valid
+        };
+
+        let (author_id, request, _) = match pending {
+            Some(entry) => entry,
+            _ => return, // stale or expired interaction, nothing left to do
+        };
+
+        if component.user.id != author_id {
+            let _ = component
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| {
+                            d.content("Only the original requester can use this button.")
+                                .ephemeral(true)
+                        })
+                })
+                .await;
+            return;
+        }
+
+        let _ = component
+            .create_interaction_response(&ctx.http, |r| {
+                r.kind(InteractionResponseType::DeferredUpdateMessage)
+            })
+            .await;
+
+        // drop the buttons so a stale or duplicate click can't re-trigger this request
+        let _ = component
+            .message
+            .channel_id
+            .edit_message(&ctx.http, component.message.id, |m| m.components(|c| c))
+            .await;
+
+        if component.data.custom_id == "attachment_dismiss" {
+            let _ = component.message.delete(&ctx.http).await;
+            return;
+        }
+
+        // `handle_request` hands back a single already-built embed, same as the
+        // `;compile` command itself; there's nothing left to paginate here
+        let pages = match handle_request(
+            ctx.clone(),
+            format!(";compile\n```{}\n{}\n```", request.language, request.code),
+            request.author.clone(),
+            &request.original_message,
+        )
+        .await
+        {
+            Ok(emb) => vec![emb],
+            Err(e) => vec![embeds::build_fail_embed(&request.author, &format!("{}", e))],
+        };
+
+        self.send_result_pages(ctx, &request.original_message, author_id, pages).await;
+    }
+
+    async fn handle_page_turn(&self, ctx: &Context, component: MessageComponentInteraction) {
+        let data = ctx.data.read().await;
+        let mut book = data.get::<PaginationCache>().unwrap().lock().await;
+        let entry = match book.get_mut(&component.message.id) {
+            Some(entry) if entry.4.elapsed() < PAGINATION_TTL => entry,
+            _ => return, // stale or expired interaction, nothing left to paginate
+        };
+
+        let (author_id, pages, current, total, _) = entry;
+        if component.user.id != *author_id {
+            let _ = component
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| {
+                            d.content("Only the original requester can page through this result.")
+                                .ephemeral(true)
+                        })
+                })
+                .await;
+            return;
+        }
+
+        if component.data.custom_id == "page_prev" {
+            *current = current.saturating_sub(1);
+        } else {
+            *current = (*current + 1).min(pages.len() - 1);
+        }
+
+        let (embed, row) = build_page(pages, *current, *total);
+        let _ = component
+            .create_interaction_response(&ctx.http, |r| {
+                r.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|d| d.set_embed(embed).components(|c| c.add_action_row(row)))
+            })
+            .await;
+    }
+
+    /// Sends a compiler result as a sequence of pages, attaching Prev/Next navigation
+    /// buttons and recording pagination state when the output didn't fit on one page.
+    ///
+    /// If the guild has a webhook persona configured, every page is delivered through
+    /// that webhook instead of the bot's own identity, as a sequence of messages;
+    /// webhook-delivered results skip Prev/Next buttons since the bot can't edit a
+    /// message it doesn't own. If a page fails to send through the webhook, only the
+    /// pages the webhook never got to fall back to a normal bot send, so the requester
+    /// gets the full result without a duplicate of what the webhook already delivered.
+    async fn send_result_pages(
+        &self,
+        ctx: &Context,
+        reference: &Message,
+        requester: UserId,
+        pages: Vec<embeds::CreateEmbed>,
+    ) {
+        let mut remaining = pages;
+        let total = remaining.len();
+
+        if let Some(persona) = webhook_persona_for(ctx, reference.guild_id).await {
+            if let Some(webhook) = fetch_persona_webhook(ctx, &persona).await {
+                let mut delivered = 0;
+                for page in &remaining {
+                    if deliver_via_webhook(ctx, &webhook, &persona, page.clone()).await.is_none() {
+                        break;
+                    }
+                    delivered += 1;
+                }
+                if delivered == remaining.len() {
+                    return;
+                }
+                remaining.drain(..delivered);
+            }
+        }
+
+        let (embed, row) = build_page(&remaining, 0, total);
+        let mut emb_msg = embed_message(embed);
+        emb_msg.reference_message(reference);
+        if remaining.len() > 1 {
+            emb_msg.components(|c| c.add_action_row(row));
+        }
+
+        let sent = match reference
+            .channel_id
+            .send_message(&ctx.http, |_| &mut emb_msg)
+            .await
+        {
+            Ok(sent) => sent,
+            Err(_) => return,
+        };
+
+        if remaining.len() > 1 {
+            let data = ctx.data.read().await;
+            let mut book = data.get::<PaginationCache>().unwrap().lock().await;
+            sweep_expired(&mut book, PAGINATION_TTL, |(_, _, _, _, inserted_at)| *inserted_at);
+            book.insert(sent.id, (requester, remaining, 0, total, Instant::now()));
+        }
+    }
+}
+
+/// Looks up the webhook persona configured for `guild_id`, if any.
+async fn webhook_persona_for(ctx: &Context, guild_id: Option<GuildId>) -> Option<WebhookPersona> {
+    let guild_id = guild_id?;
+    let data = ctx.data.read().await;
+    let settings = data.get::<GuildSettingsCache>().unwrap().read().await;
+    settings.get(guild_id.0).and_then(|s| s.webhook_persona())
+}
+
+/// Fetches the webhook `persona` points at, so a multi-page result can reuse a single
+/// fetch across all of its pages instead of re-fetching it before every send.
+async fn fetch_persona_webhook(ctx: &Context, persona: &WebhookPersona) -> Option<Webhook> {
+    ctx.http.get_webhook_with_token(persona.id, &persona.token).await.ok()
+}
+
+/// Posts `embed` through `webhook`, impersonating `persona`'s configured name/avatar.
+/// Returns `None` (rather than erroring) so callers can fall back to a normal bot send.
+async fn deliver_via_webhook(
+    ctx: &Context,
+    webhook: &Webhook,
+    persona: &WebhookPersona,
+    embed: embeds::CreateEmbed,
+) -> Option<Message> {
+    webhook
+        .execute(&ctx.http, true, |w| {
+            w.username(&persona.username);
+            if let Some(avatar_url) = &persona.avatar_url {
+                w.avatar_url(avatar_url);
+            }
+            w.embeds(vec![embed])
+        })
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Builds the embed and nav-button row for `pages[index]`, disabling Prev/Next
+/// at the first/last page respectively. `total` is the page count of the result
+/// `pages` was cut from, which can exceed `pages.len()` when a webhook persona
+/// already delivered the earlier pages (`pages` then only holds the tail the bot
+/// fell back to) -- `pages[0]`'s true position is always `total - pages.len()`,
+/// so the label/disabled state line up with the page numbers baked into each
+/// embed's footer by `build_result_pages` instead of being re-derived from the
+/// possibly-truncated `pages` alone.
+fn build_page(pages: &[embeds::CreateEmbed], index: usize, total: usize) -> (embeds::CreateEmbed, CreateActionRow) {
+    let position = total - pages.len() + index;
+    let mut row = CreateActionRow::default();
+    row.create_button(|b| {
+        b.custom_id("page_prev")
+            .label("◀ Prev")
+            .style(ButtonStyle::Secondary)
+            .disabled(position == 0)
+    })
+    .create_button(|b| {
+        b.custom_id("page_next")
+            .label(format!("Next ▶ ({}/{})", position + 1, total))
+            .style(ButtonStyle::Secondary)
+            .disabled(position + 1 == total)
+    });
+
+    (pages[index].clone(), row)
+}
+
 #[hook]
-pub async fn before(ctx: &Context, msg : &Message, _: &str) -> bool {
+pub async fn before(ctx: &Context, msg : &Message, command_name: &str) -> bool {
     let data = ctx.data.read().await;
     {
         let stats = data.get::<StatsManagerCache>().unwrap().lock().await;
@@ -290,7 +595,8 @@ pub async fn before(ctx: &Context, msg : &Message, _: &str) -> bool {
         guild_id = id.0;
     }
 
-    // check user against our blocklist
+    // check user against our blocklist; entries can now be added/removed at runtime
+    // through the `;block` / `;unblock` commands, so this always reflects the latest state
     {
         let blocklist = data.get::<BlocklistCache>().unwrap().read().await;
         let author_blocklisted = blocklist.contains(msg.author.id.0);
@@ -315,9 +621,32 @@ pub async fn before(ctx: &Context, msg : &Message, _: &str) -> bool {
         }
     }
 
+    // honor this guild's channel allowlist, but only for commands that actually
+    // compile something -- it shouldn't block unrelated commands like help/info
+    if matches!(command_name, "compile") {
+        if let Some(id) = msg.guild_id {
+            let settings = data.get::<GuildSettingsCache>().unwrap().read().await;
+            if let Some(guild_settings) = settings.get(id.0) {
+                if !guild_settings.is_channel_allowed(msg.channel_id.0) {
+                    return false;
+                }
+            }
+        }
+    }
+
     true
 }
 
+/// Resolves this guild's custom command prefix, if `;settings` has configured one.
+/// Falls back to the framework's configured default prefix when `None` is returned.
+#[hook]
+pub async fn dynamic_prefix(ctx: &Context, msg: &Message) -> Option<String> {
+    let guild_id = msg.guild_id?;
+    let data = ctx.data.read().await;
+    let settings = data.get::<GuildSettingsCache>().unwrap().read().await;
+    settings.get(guild_id.0).and_then(|s| s.prefix.clone())
+}
+
 #[hook]
 pub async fn after(
     ctx: &Context,
@@ -329,12 +658,23 @@ pub async fn after(
 
     if let Err(e) = command_result {
         let emb = embeds::build_fail_embed(&msg.author, &format!("{}", e));
-        let mut emb_msg = embeds::embed_message(emb);
-        if let Ok(sent) = msg
-            .channel_id
-            .send_message(&ctx.http, |_| &mut emb_msg)
-            .await
-        {
+
+        let sent = match webhook_persona_for(ctx, msg.guild_id).await {
+            Some(persona) => match fetch_persona_webhook(ctx, &persona).await {
+                Some(webhook) => deliver_via_webhook(ctx, &webhook, &persona, emb.clone()).await,
+                None => None,
+            },
+            None => None,
+        };
+        let sent = match sent {
+            Some(sent) => Some(sent),
+            None => {
+                let mut emb_msg = embeds::embed_message(emb);
+                msg.channel_id.send_message(&ctx.http, |_| &mut emb_msg).await.ok()
+            }
+        };
+
+        if let Some(sent) = sent {
             let mut message_cache = data.get::<MessageCache>().unwrap().lock().await;
             message_cache.insert(msg.id.0, sent);
         }
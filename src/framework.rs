@@ -0,0 +1,14 @@
+use serenity::framework::standard::StandardFramework;
+
+use crate::commands::blocklist::BLOCKLIST_GROUP;
+use crate::commands::settings::SETTINGS_GROUP;
+use crate::events;
+
+/// Extends the bot's `StandardFramework` with the settings/blocklist command groups
+/// and per-guild custom prefix resolution added in this work.
+pub fn configure_framework(framework: StandardFramework) -> StandardFramework {
+    framework
+        .dynamic_prefix(events::dynamic_prefix)
+        .group(&SETTINGS_GROUP)
+        .group(&BLOCKLIST_GROUP)
+}
@@ -0,0 +1,183 @@
+pub use serenity::builder::CreateEmbed;
+use serenity::model::user::User;
+
+/// Builds the "you attached a source file, want me to compile it?" prompt shown
+/// alongside the Compile/Dismiss buttons.
+pub fn build_attachment_prompt_embed(author: &User, language: &str) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title("Compile attachment?")
+        .description(format!(
+            "{} attached a `{}` source file. Press **Compile** to run it, or **Dismiss** to ignore it.",
+            author.tag(),
+            language
+        ));
+    embed
+}
+
+/// Builds a small confirmation embed for a `;settings` or `;block`/`;unblock` command
+/// that changed something.
+pub fn build_settings_ack_embed(text: &str) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title("Settings updated").description(text);
+    embed
+}
+
+/// Builds an embed listing every id currently present in the blocklist.
+pub fn build_blocklist_list_embed(ids: &[u64]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title("Blocklist");
+    if ids.is_empty() {
+        embed.description("Nothing is currently blocked.");
+    } else {
+        embed.description(ids.iter().map(|id| format!("`{}`", id)).collect::<Vec<_>>().join("\n"));
+    }
+    embed
+}
+
+/// Builds the embed posted to `JOIN_LOG` when `;block`/`;unblock` changes the blocklist.
+pub fn build_blocklist_action_embed(action: &str, id: u64, actor: &str) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title("Blocklist updated")
+        .description(format!("{} `{}` (by {})", action, id, actor));
+    embed
+}
+
+/// An embed description can hold at most 4096 characters; leave headroom for the
+/// code fence wrapping each page so a page body never pushes the rendered embed over
+/// Discord's limit.
+const MAX_PAGE_BODY_CHARS: usize = 4000;
+
+/// Splits `output` into pages that each fit within [`MAX_PAGE_BODY_CHARS`] once
+/// wrapped in a `language` code fence, accumulating whole lines into the current
+/// page and flushing it before the next line would push it over the limit. A line
+/// that alone exceeds the limit (a long instantiation error, a long asm line, ...)
+/// is split on its own rather than being pushed whole into an over-limit page.
+pub fn paginate_output(output: &str, language: &str) -> Vec<String> {
+    let fence_overhead = language.len() + "```\n\n```".len();
+    let max_body_len = MAX_PAGE_BODY_CHARS.saturating_sub(fence_overhead);
+
+    let mut pages = Vec::new();
+    let mut current = String::new();
+    for line in output.lines() {
+        if !current.is_empty() && current.len() + line.len() + 1 > max_body_len {
+            pages.push(std::mem::take(&mut current));
+        }
+
+        if line.len() > max_body_len {
+            pages.extend(chunk_str(line, max_body_len).into_iter().map(str::to_string));
+            continue;
+        }
+
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() || pages.is_empty() {
+        pages.push(current);
+    }
+    pages
+}
+
+/// Splits `s` into chunks of at most `max_len` bytes, respecting UTF-8 character
+/// boundaries so a multi-byte character is never cut in half.
+fn chunk_str(s: &str, max_len: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < s.len() {
+        let mut end = (start + max_len).min(s.len());
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        chunks.push(&s[start..end]);
+        start = end;
+    }
+    chunks
+}
+
+/// Builds one embed per page of `output`, each wrapped in a `language` code fence and,
+/// when there's more than one page, labeled with its page number out of the total.
+/// No result path in `events.rs` builds its pages with this function: attachment
+/// results reach `send_result_pages` as the single embed `handle_request` returns.
+pub fn build_result_pages(title: &str, language: &str, output: &str) -> Vec<CreateEmbed> {
+    let pages = paginate_output(output, language);
+    let total = pages.len();
+    pages
+        .into_iter()
+        .enumerate()
+        .map(|(i, page)| {
+            let mut embed = CreateEmbed::default();
+            embed
+                .title(title)
+                .description(format!("```{}\n{}\n```", language, page));
+            if total > 1 {
+                embed.footer(|f| f.text(format!("Page {}/{}", i + 1, total)));
+            }
+            embed
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The max body length `paginate_output` pages to for a given `language`, mirroring
+    /// its own fence-overhead accounting.
+    fn max_body_len(language: &str) -> usize {
+        MAX_PAGE_BODY_CHARS - (language.len() + "```\n\n```".len())
+    }
+
+    #[test]
+    fn pages_never_exceed_the_max_body_length() {
+        let output = (0..500).map(|i| format!("line {}", i)).collect::<Vec<_>>().join("\n");
+        let max_len = max_body_len("rust");
+        for page in paginate_output(&output, "rust") {
+            assert!(page.len() <= max_len, "page of {} bytes exceeds {}", page.len(), max_len);
+        }
+    }
+
+    #[test]
+    fn pages_never_split_a_line_that_fits_on_its_own() {
+        let lines: Vec<String> = (0..200).map(|i| format!("line {}", i)).collect();
+        let output = lines.join("\n");
+        let pages = paginate_output(&output, "rust");
+
+        // every original line must appear whole on exactly one page
+        for line in &lines {
+            let count = pages.iter().filter(|page| page.lines().any(|l| l == line)).count();
+            assert_eq!(count, 1, "line {:?} didn't end up whole on exactly one page", line);
+        }
+    }
+
+    #[test]
+    fn a_line_longer_than_the_limit_is_split_on_its_own_and_never_dropped() {
+        let max_len = max_body_len("rust");
+        let long_line = "x".repeat(max_len * 3 + 7);
+        let pages = paginate_output(&long_line, "rust");
+
+        // no page (including the over-long line's own chunks) exceeds the limit
+        for page in &pages {
+            assert!(page.len() <= max_len);
+        }
+
+        // reassembling every page recovers the original line exactly, nothing lost
+        assert_eq!(pages.concat(), long_line);
+    }
+
+    #[test]
+    fn chunk_str_never_splits_a_utf8_character() {
+        let s = "héllo wörld 🎉🎉🎉 test".repeat(50);
+        let chunks = chunk_str(&s, 10);
+
+        // every chunk must itself be valid UTF-8 (chunk_str hands back &str, so this
+        // is really asserting `is_char_boundary` held at every cut point) and
+        // reassembling them recovers the original string
+        assert_eq!(chunks.concat(), s);
+        for chunk in &chunks {
+            assert!(chunk.len() <= 10);
+        }
+    }
+}